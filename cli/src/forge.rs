@@ -19,10 +19,28 @@ mod forge_opts;
 use forge_opts::{EvmType, Opts, Subcommands};
 
 use crate::forge_opts::{Dependency, FullContractInfo};
-use std::{collections::HashMap, convert::TryFrom, process::Command, str::FromStr, sync::Arc};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    process::Command,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
 
 mod cmd;
+mod lockfile;
+mod progress;
+mod template;
 mod utils;
+mod vcs;
+
+use lockfile::{LockedDependency, Lockfile};
+use template::Context;
+use vcs::VcsBackend;
+
+/// How many dependencies are installed concurrently. Chosen to keep well clear of most git
+/// servers' per-IP connection limits while still overlapping the network-bound clones.
+const INSTALL_CONCURRENCY: usize = 4;
 
 #[tracing::instrument(err)]
 fn main() -> eyre::Result<()> {
@@ -149,26 +167,53 @@ fn main() -> eyre::Result<()> {
         Subcommands::Update { lib } => {
             // TODO: Should we add some sort of progress bar here? Would be nice
             // but not a requirement.
-            // open the repo
-            let repo = git2::Repository::open(".")?;
-
-            // if a lib is specified, open it
-            if let Some(lib) = lib {
-                println!("Updating submodule {:?}", lib);
-                repo.find_submodule(
-                    &lib.into_os_string().into_string().expect("invalid submodule path"),
-                )?
-                .update(true, None)?;
+            let root = std::env::current_dir()?;
+            let mut lockfile = Lockfile::load(&root)?;
+            let libs = std::path::Path::new("lib");
+
+            let locked: Vec<LockedDependency> = match &lib {
+                Some(lib) => {
+                    let name = lib.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+                    lockfile.get(name).cloned().into_iter().collect()
+                }
+                None => lockfile.dependencies.clone(),
+            };
+
+            if locked.is_empty() {
+                // no `forge.lock` entry to re-resolve against: fall back to a plain submodule
+                // update
+                let backend = vcs::backend_for(None, None);
+                backend.update(lib.as_deref())?;
             } else {
-                Command::new("git")
-                    .args(&["submodule", "update", "--init", "--recursive"])
-                    .spawn()?
-                    .wait()?;
+                for dependency in locked {
+                    let path = libs.join(&dependency.name);
+                    let backend = vcs::backend_for(Some(dependency.vcs), None);
+                    let rev = backend.resolve_rev(&dependency.url, &dependency.requested)?;
+                    println!("Updating {} to {} ({})", dependency.name, rev, dependency.requested);
+
+                    // on a freshly cloned superproject, `path` doesn't exist on disk at all until
+                    // this materializes it: `git submodule update --init` must run before
+                    // `checkout` can `current_dir` into it
+                    backend.update(Some(&path)).map_err(|err| {
+                        eyre::eyre!("failed to initialize submodules of {}: {}", dependency.name, err)
+                    })?;
+
+                    backend.checkout(&path, &rev)?;
+
+                    // the new revision may have changed which nested submodules the dependency
+                    // itself relies on, so re-initialize them rather than leaving stale ones
+                    backend.update(Some(&path)).map_err(|err| {
+                        eyre::eyre!("failed to initialize submodules of {}: {}", dependency.name, err)
+                    })?;
+
+                    lockfile.upsert(LockedDependency { rev, ..dependency });
+                }
+                lockfile.save(&root)?;
             }
         }
         // TODO: Make it work with updates?
-        Subcommands::Install { dependencies } => {
-            install(std::env::current_dir()?, dependencies)?;
+        Subcommands::Install { dependencies, vcs } => {
+            install(std::env::current_dir()?, dependencies, vcs)?;
         }
         Subcommands::Remappings { lib_paths, root } => {
             let root = root.unwrap_or_else(|| std::env::current_dir().unwrap());
@@ -182,20 +227,45 @@ fn main() -> eyre::Result<()> {
                 .collect();
             remappings.iter().for_each(|x| println!("{}", x));
         }
-        Subcommands::Init { root, template } => {
+        Subcommands::Init { root, template, name, author, license } => {
             let root = root.unwrap_or_else(|| std::env::current_dir().unwrap());
             // create the root dir if it does not exist
             if !root.exists() {
                 std::fs::create_dir_all(&root)?;
             }
             let root = std::fs::canonicalize(root)?;
+            let ctx = Context::new(&root, name, author, license);
 
-            // if a template is provided, then this command is just an alias to `git clone <url>
-            // <path>`
+            // if a template is provided, then this command is just an alias to cloning the
+            // template's repository, with its placeholders filled in afterwards
             if let Some(ref template) = template {
                 println!("Initializing {} from {}...", root.display(), template);
+                let backend = vcs::backend_for(None, None);
+                // clone non-recursively: `--recursive` would check nested submodules out as part
+                // of the clone itself, before `expand_tree` below gets a chance to run, and
+                // placeholder substitution would then walk into vendored dependency source (e.g.
+                // ds-test/forge-std) instead of just the template's own files
+                backend.clone(template, &root, false)?;
+                std::env::set_current_dir(&root)?;
+
+                // expand placeholders before initializing nested submodules, while they're still
+                // uninitialized, empty directories
+                ctx.expand_tree(&root)?;
+
+                // now that placeholders are expanded, initialize nested submodules the template
+                // adds; surfaces a clear error instead of leaving a half-initialized `lib/` tree
+                // if one is unreachable.
+                backend
+                    .update(None)
+                    .map_err(|err| eyre::eyre!("failed to initialize submodules of {}: {}", template, err))?;
+                // drop the template's history so the result is a fresh project, not a fork of it
+                std::fs::remove_dir_all(root.join(".git"))?;
+
+                Command::new("git").arg("init").current_dir(&root).spawn()?.wait()?;
+                Command::new("git").args(&["add", "."]).current_dir(&root).spawn()?.wait()?;
                 Command::new("git")
-                    .args(&["clone", template, &root.display().to_string()])
+                    .args(&["commit", "-m", "chore: forge init from template"])
+                    .current_dir(&root)
                     .spawn()?
                     .wait()?;
             } else {
@@ -210,10 +280,16 @@ fn main() -> eyre::Result<()> {
 
                 // write the contract file
                 let contract_path = src.join("Contract.sol");
-                std::fs::write(contract_path, include_str!("../../assets/ContractTemplate.sol"))?;
+                std::fs::write(
+                    contract_path,
+                    ctx.render(include_str!("../../assets/ContractTemplate.sol")),
+                )?;
                 // write the tests
                 let contract_path = test.join("Contract.t.sol");
-                std::fs::write(contract_path, include_str!("../../assets/ContractTemplate.t.sol"))?;
+                std::fs::write(
+                    contract_path,
+                    ctx.render(include_str!("../../assets/ContractTemplate.t.sol")),
+                )?;
 
                 // sets up git
                 Command::new("git").arg("init").current_dir(&root).spawn()?.wait()?;
@@ -225,7 +301,7 @@ fn main() -> eyre::Result<()> {
                     .wait()?;
 
                 Dependency::from_str("https://github.com/dapphub/ds-test")
-                    .and_then(|dependency| install(root, vec![dependency]))?;
+                    .and_then(|dependency| install(root, vec![dependency], None))?;
             }
 
             println!("Done.");
@@ -332,44 +408,158 @@ fn test<A: ArtifactOutput + 'static, S: Clone, E: evm_adapters::Evm<S>>(
     std::process::exit(exit_code);
 }
 
-fn install(root: impl AsRef<std::path::Path>, dependencies: Vec<Dependency>) -> eyre::Result<()> {
-    let libs = std::path::Path::new("lib");
-
-    dependencies.iter().try_for_each(|dep| -> eyre::Result<_> {
-        let path = libs.join(&dep.name);
-        println!("Installing {} in {:?}, (url: {}, tag: {:?})", dep.name, path, dep.url, dep.tag);
-
-        // install the dep
+/// Installs `dependencies` with up to [`INSTALL_CONCURRENCY`] of them in flight at once.
+///
+/// The network-bound phases (resolving the revision and cloning) run fully in parallel. The
+/// phases that mutate the outer repository's git index (registering the submodule, `git add`,
+/// `git commit`) are serialized via `lockfile`'s mutex so concurrent installs can't corrupt it.
+/// One dependency failing doesn't abort the others; failures are collected and reported in a
+/// summary at the end.
+fn install(
+    root: impl AsRef<std::path::Path>,
+    dependencies: Vec<Dependency>,
+    vcs: Option<forge_opts::VcsKind>,
+) -> eyre::Result<()> {
+    let root = root.as_ref().to_path_buf();
+    std::env::set_current_dir(&root)?;
+    let lockfile = Mutex::new(Lockfile::load(&root)?);
+    let tracker = progress::Tracker::new();
+
+    let results: Vec<(String, eyre::Result<()>)> = std::thread::scope(|scope| {
+        dependencies
+            .chunks(INSTALL_CONCURRENCY.max(1))
+            .flat_map(|batch| {
+                let handles: Vec<_> = batch
+                    .iter()
+                    .map(|dep| {
+                        let lockfile = &lockfile;
+                        let tracker = &tracker;
+                        scope.spawn(move || {
+                            // a panicking dependency must not take the rest of the batch down
+                            // with it: catch it here and report it as a plain failure instead of
+                            // letting it unwind through `join` below
+                            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(
+                                || install_one(dep, vcs, lockfile, tracker),
+                            ))
+                            .unwrap_or_else(|payload| {
+                                Err(eyre::eyre!("panicked: {}", panic_message(payload.as_ref())))
+                            });
+                            (dep.name.clone(), result)
+                        })
+                    })
+                    .collect();
+                handles.into_iter().map(|handle| handle.join().expect("install worker panicked"))
+            })
+            .collect()
+    });
+
+    lockfile.into_inner().unwrap_or_else(std::sync::PoisonError::into_inner).save(&root)?;
+
+    // stage and commit the lockfile itself, so cloning the superproject elsewhere actually gets a
+    // `forge.lock` instead of relying on each dependency's commit to have picked it up
+    Command::new("git").args(&["add", lockfile::FILE_NAME]).spawn()?.wait()?;
+    if !Command::new("git").args(&["diff", "--cached", "--quiet"]).status()?.success() {
         Command::new("git")
-            .args(&["submodule", "add", &dep.url, &path.display().to_string()])
-            .current_dir(&root)
+            .args(&["commit", "-m", "forge install: update forge.lock"])
             .spawn()?
             .wait()?;
+    }
 
-        // call update on it
-        Command::new("git")
-            .args(&["submodule", "update", "--init", "--recursive", &path.display().to_string()])
-            .current_dir(&root)
-            .spawn()?
-            .wait()?;
+    let total = results.len();
+    let (succeeded, failed): (Vec<_>, Vec<_>) =
+        results.into_iter().partition(|(_, result)| result.is_ok());
+    println!("\n{} succeeded, {} failed", succeeded.len(), failed.len());
+    for (name, result) in &failed {
+        if let Err(err) = result {
+            println!("  {}: {}", name, err);
+        }
+    }
 
-        // checkout the tag if necessary
-        let message = if let Some(ref tag) = dep.tag {
-            Command::new("git")
-                .args(&["checkout", "--recurse-submodules", tag])
-                .current_dir(&path)
-                .spawn()?
-                .wait()?;
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        eyre::bail!("{} of {} dependencies failed to install", failed.len(), total)
+    }
+}
 
-            Command::new("git").args(&["add", &path.display().to_string()]).spawn()?.wait()?;
+/// Installs a single dependency, reporting its progress through `tracker` and locking around the
+/// steps that touch the outer repository's git state.
+fn install_one(
+    dep: &Dependency,
+    vcs: Option<forge_opts::VcsKind>,
+    lockfile: &Mutex<Lockfile>,
+    tracker: &progress::Tracker,
+) -> eyre::Result<()> {
+    let libs = std::path::Path::new("lib");
+    let path = libs.join(&dep.name);
+    let backend = vcs::backend_for(dep.vcs, vcs);
+    let vcs_kind = dep.vcs.or(vcs).unwrap_or(forge_opts::VcsKind::Git);
+    let bar = tracker.spinner(&dep.name);
+
+    // an existing lock entry pins the dependency to a reproducible commit; otherwise resolve the
+    // requested tag/branch/rev against the remote and lock the result
+    progress::advance(&bar, progress::Phase::Resolve);
+    let existing = lock_lockfile(lockfile).get(&dep.name).cloned();
+    let rev = match existing {
+        // only trust the lock entry if it was pinned against the same spec that was just
+        // requested; otherwise e.g. `forge install foo@v2.0.0` over a lock entry requested at
+        // `v1.0.0` would silently keep the old commit while still rewriting `requested` to
+        // `v2.0.0`, leaving the lockfile asserting that `v2.0.0` resolved to `v1.0.0`'s sha
+        Some(locked) if locked.requested == dep.requested() => locked.rev,
+        _ => backend.resolve_rev(&dep.url, dep.requested())?,
+    };
+
+    progress::advance(&bar, progress::Phase::Clone);
+    backend.clone(&dep.url, &path, true)?;
+
+    progress::advance(&bar, progress::Phase::Checkout);
+    backend.checkout(&path, &rev)?;
+
+    progress::advance(&bar, progress::Phase::Register);
+    {
+        let mut lockfile = lock_lockfile(lockfile);
+
+        backend.add_dependency(&dep.url, &path)?;
+
+        // the dependency may itself depend on nested submodules (e.g. ds-test, forge-std);
+        // initialize them now instead of leaving an empty directory for the user to discover
+        backend
+            .update(Some(&path))
+            .map_err(|err| eyre::eyre!("failed to initialize submodules of {}: {}", dep.name, err))?;
+
+        Command::new("git").args(&["add", &path.display().to_string()]).spawn()?.wait()?;
+
+        lockfile.upsert(LockedDependency {
+            name: dep.name.clone(),
+            url: dep.url.clone(),
+            requested: dep.requested().to_string(),
+            rev: rev.clone(),
+            vcs: vcs_kind,
+        });
+
+        let message = format!("forge install: {}\n\n{}", dep.name, rev);
+        Command::new("git").args(&["commit", "-m", &message]).spawn()?.wait()?;
+    }
 
-            format!("forge install: {}\n\n{}", dep.name, tag)
-        } else {
-            format!("forge install: {}", dep.name)
-        };
+    progress::advance(&bar, progress::Phase::Done);
+    bar.finish();
+    Ok(())
+}
 
-        Command::new("git").args(&["commit", "-m", &message]).current_dir(&root).spawn()?.wait()?;
+/// Locks `lockfile`, recovering the guard instead of panicking if some other worker poisoned it.
+/// A panic while installing one dependency must not take down every other dependency that still
+/// needs to read or write the lockfile.
+fn lock_lockfile(lockfile: &Mutex<Lockfile>) -> std::sync::MutexGuard<'_, Lockfile> {
+    lockfile.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+}
 
-        Ok(())
-    })
+/// Best-effort extraction of a human-readable message from a `catch_unwind` payload.
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
 }
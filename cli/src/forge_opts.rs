@@ -0,0 +1,280 @@
+use ethers::{
+    solc::EvmVersion,
+    types::{Address, U256},
+};
+use evm_adapters::sputnik::SputnikVicinity;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{convert::TryFrom, path::PathBuf, str::FromStr};
+use structopt::{clap::Shell, StructOpt};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "forge")]
+pub struct Opts {
+    #[structopt(subcommand)]
+    pub sub: Subcommands,
+}
+
+#[derive(Debug, StructOpt)]
+pub enum Subcommands {
+    #[structopt(about = "test your smart contracts")]
+    Test {
+        #[structopt(flatten)]
+        opts: BuildOpts,
+        #[structopt(flatten)]
+        env: Env,
+        #[structopt(long, short, help = "print the test results in json format")]
+        json: bool,
+        #[structopt(
+            long = "match",
+            short = "m",
+            help = "only run test methods matching regex",
+            default_value = ".*"
+        )]
+        pattern: Regex,
+        #[structopt(long, default_value = "sputnik")]
+        evm_type: EvmType,
+        #[structopt(long, help = "fetch state over a remote node")]
+        fork_url: Option<String>,
+        #[structopt(long, help = "block number to fork from, defaults to latest")]
+        fork_block_number: Option<u64>,
+        #[structopt(long, help = "the initial balance of deployed contracts")]
+        initial_balance: U256,
+        #[structopt(long, help = "the address which will be executing all tests")]
+        sender: Address,
+        #[structopt(long, help = "enables the FFI cheatcode")]
+        ffi: bool,
+        #[structopt(long, short, parse(from_occurrences))]
+        verbosity: u8,
+        #[structopt(long, help = "exit with code 0 even if a test fails")]
+        allow_failure: bool,
+    },
+    #[structopt(about = "build your smart contracts")]
+    Build {
+        #[structopt(flatten)]
+        opts: BuildOpts,
+    },
+    #[structopt(about = "verify your smart contract on Etherscan")]
+    VerifyContract {
+        contract: FullContractInfo,
+        address: Address,
+        constructor_args: Vec<String>,
+    },
+    #[structopt(about = "deploy a smart contract")]
+    Create {
+        contract: FullContractInfo,
+        #[structopt(long)]
+        verify: bool,
+    },
+    #[structopt(about = "update one or all dependencies")]
+    Update {
+        #[structopt(help = "the submodule name of the library you want to update")]
+        lib: Option<PathBuf>,
+    },
+    #[structopt(about = "install one or more dependencies")]
+    Install {
+        #[structopt(help = "the dependencies you want to install")]
+        dependencies: Vec<Dependency>,
+        #[structopt(
+            long,
+            help = "the vcs backend to use for dependencies that don't specify one themselves"
+        )]
+        vcs: Option<VcsKind>,
+    },
+    #[structopt(about = "prints the automatically inferred remappings for this repository")]
+    Remappings {
+        #[structopt(long, help = "the paths to the libraries")]
+        lib_paths: Vec<PathBuf>,
+        #[structopt(long)]
+        root: Option<PathBuf>,
+    },
+    #[structopt(about = "create a new forge project")]
+    Init {
+        #[structopt(help = "the project's root path, default being the current working directory")]
+        root: Option<PathBuf>,
+        #[structopt(long, help = "optional git repository to template the project from")]
+        template: Option<String>,
+        #[structopt(long, help = "the project name used to fill in {{ name }} placeholders")]
+        name: Option<String>,
+        #[structopt(long, help = "the project author used to fill in {{ author }} placeholders")]
+        author: Option<String>,
+        #[structopt(long, help = "the project license used to fill in {{ license }} placeholders")]
+        license: Option<String>,
+    },
+    #[structopt(about = "generate shell completions script")]
+    Completions { shell: Shell },
+    #[structopt(about = "removes the build artifacts and cache directories")]
+    Clean {
+        #[structopt(long)]
+        root: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, StructOpt)]
+pub struct BuildOpts {
+    #[structopt(help = "the project's root path, default being the current working directory")]
+    pub root: Option<PathBuf>,
+    #[structopt(long, help = "additional paths to the libraries")]
+    pub lib_paths: Vec<PathBuf>,
+    #[structopt(long, default_value = "london")]
+    pub evm_version: EvmVersionOpt,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct EvmVersionOpt(pub EvmVersion);
+
+impl EvmVersionOpt {
+    pub fn sputnik_cfg(&self) -> sputnik::Config {
+        self.0.sputnik_cfg()
+    }
+
+    pub fn evmodin_cfg(&self) -> evmodin::Revision {
+        self.0.evmodin_cfg()
+    }
+}
+
+impl FromStr for EvmVersionOpt {
+    type Err = eyre::Error;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        Ok(EvmVersionOpt(EvmVersion::from_str(s).map_err(|err| eyre::eyre!(err))?))
+    }
+}
+
+impl TryFrom<&BuildOpts> for ethers::solc::Project {
+    type Error = eyre::Error;
+
+    fn try_from(opts: &BuildOpts) -> eyre::Result<Self> {
+        let root = opts.root.clone().unwrap_or_else(|| std::env::current_dir().unwrap());
+        let root = std::fs::canonicalize(root)?;
+        let paths = ethers::solc::ProjectPathsConfig::builder()
+            .root(&root)
+            .lib_paths(opts.lib_paths.clone())
+            .build()?;
+        Ok(ethers::solc::Project::builder().paths(paths).build()?)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Env {
+    pub gas_limit: u64,
+}
+
+impl Env {
+    pub fn sputnik_state(&self) -> SputnikVicinity {
+        SputnikVicinity::default()
+    }
+
+    pub fn evmodin_state(&self) -> evmodin::host::MockedHost {
+        evmodin::host::MockedHost::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum EvmType {
+    #[cfg(feature = "sputnik-evm")]
+    Sputnik,
+    #[cfg(feature = "evmodin-evm")]
+    EvmOdin,
+}
+
+impl FromStr for EvmType {
+    type Err = eyre::Error;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        match s.to_lowercase().as_str() {
+            #[cfg(feature = "sputnik-evm")]
+            "sputnik" => Ok(EvmType::Sputnik),
+            #[cfg(feature = "evmodin-evm")]
+            "evmodin" => Ok(EvmType::EvmOdin),
+            other => eyre::bail!("unknown evm type `{}`", other),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FullContractInfo {
+    pub path: String,
+    pub name: String,
+}
+
+impl FromStr for FullContractInfo {
+    type Err = eyre::Error;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        let mut iter = s.split(':');
+        let path =
+            iter.next().ok_or_else(|| eyre::eyre!("contract info must be in the form `path:name`"))?;
+        let name =
+            iter.next().ok_or_else(|| eyre::eyre!("contract info must be in the form `path:name`"))?;
+        Ok(Self { path: path.to_string(), name: name.to_string() })
+    }
+}
+
+/// The version control system a dependency is hosted under.
+///
+/// Defaults to [`VcsKind::Git`] unless a dependency's url carries an explicit `hg+` prefix or
+/// `--vcs` is passed on the command line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VcsKind {
+    Git,
+    Mercurial,
+}
+
+impl FromStr for VcsKind {
+    type Err = eyre::Error;
+
+    fn from_str(s: &str) -> eyre::Result<Self> {
+        match s.to_lowercase().as_str() {
+            "git" => Ok(VcsKind::Git),
+            "mercurial" | "hg" => Ok(VcsKind::Mercurial),
+            other => eyre::bail!("unsupported vcs backend `{}`, expected `git` or `mercurial`", other),
+        }
+    }
+}
+
+/// A dependency as specified on the command line, e.g.
+/// `https://github.com/dapphub/ds-test`, `https://github.com/dapphub/ds-test@v1.0.0`,
+/// `https://github.com/dapphub/ds-test@master`, or (pinning an exact commit, like Cargo's git
+/// dependencies) `https://github.com/dapphub/ds-test@rev=e282159`.
+///
+/// The url may be prefixed with `hg+` to pin the dependency to the [`Mercurial`][VcsKind::Mercurial]
+/// backend regardless of the `--vcs` flag passed to `forge install`.
+#[derive(Debug, Clone)]
+pub struct Dependency {
+    pub name: String,
+    pub url: String,
+    /// The tag, branch, or `rev=<sha>` spec requested on the command line, if any. Resolved to a
+    /// concrete commit and pinned in `forge.lock` by [`crate::lockfile`].
+    pub spec: Option<String>,
+    pub vcs: Option<VcsKind>,
+}
+
+impl Dependency {
+    /// The spec to resolve against the remote, `HEAD` if none was requested.
+    pub fn requested(&self) -> &str {
+        self.spec.as_deref().unwrap_or("HEAD")
+    }
+}
+
+impl FromStr for Dependency {
+    type Err = eyre::Error;
+
+    fn from_str(dependency: &str) -> eyre::Result<Self> {
+        let (vcs, dependency) = match dependency.strip_prefix("hg+") {
+            Some(rest) => (Some(VcsKind::Mercurial), rest),
+            None => (None, dependency),
+        };
+        let (url, spec) = match dependency.split_once('@') {
+            Some((url, spec)) => (url, Some(spec.to_string())),
+            None => (dependency, None),
+        };
+        let name = url
+            .split('/')
+            .last()
+            .ok_or_else(|| eyre::eyre!("no dependency name found for {}", dependency))?
+            .to_string();
+        Ok(Dependency { name, url: url.to_string(), spec, vcs })
+    }
+}
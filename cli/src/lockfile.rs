@@ -0,0 +1,56 @@
+//! `forge.lock` pins every dependency to the exact commit it was resolved to, so `forge install`
+//! is reproducible instead of tracking whatever the submodule happens to point to.
+//!
+//! Each entry records the spec that was requested (a tag, branch, or `rev=<sha>`) alongside the
+//! concrete revision it resolved to, so `forge update` can re-resolve the same spec later without
+//! needing to re-derive it from `.gitmodules`.
+
+use crate::forge_opts::VcsKind;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+pub const FILE_NAME: &str = "forge.lock";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Lockfile {
+    #[serde(rename = "dependency", default)]
+    pub dependencies: Vec<LockedDependency>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LockedDependency {
+    pub name: String,
+    pub url: String,
+    pub requested: String,
+    pub rev: String,
+    pub vcs: VcsKind,
+}
+
+impl Lockfile {
+    /// Loads `forge.lock` from `root`, returning an empty lockfile if none exists yet.
+    pub fn load(root: impl AsRef<Path>) -> eyre::Result<Self> {
+        let path = root.as_ref().join(FILE_NAME);
+        if !path.exists() {
+            return Ok(Self::default())
+        }
+        let contents = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&contents)?)
+    }
+
+    pub fn save(&self, root: impl AsRef<Path>) -> eyre::Result<()> {
+        std::fs::write(root.as_ref().join(FILE_NAME), toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LockedDependency> {
+        self.dependencies.iter().find(|dep| dep.name == name)
+    }
+
+    /// Inserts `dependency`, replacing any existing entry with the same name.
+    pub fn upsert(&mut self, dependency: LockedDependency) {
+        match self.dependencies.iter_mut().find(|dep| dep.name == dependency.name) {
+            Some(existing) => *existing = dependency,
+            None => self.dependencies.push(dependency),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! Per-dependency progress reporting for concurrent `forge install`.
+
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+
+/// The stages a dependency moves through while it's being installed.
+#[derive(Debug, Clone, Copy)]
+pub enum Phase {
+    Resolve,
+    Clone,
+    Checkout,
+    Register,
+    Done,
+}
+
+impl Phase {
+    fn label(self) -> &'static str {
+        match self {
+            Phase::Resolve => "resolving revision",
+            Phase::Clone => "cloning",
+            Phase::Checkout => "checking out",
+            Phase::Register => "registering submodule",
+            Phase::Done => "done",
+        }
+    }
+}
+
+/// Owns one spinner per dependency, all rendered under a shared [`MultiProgress`] so concurrent
+/// installs are visible at once instead of interleaving raw output.
+pub struct Tracker {
+    multi: MultiProgress,
+}
+
+impl Tracker {
+    pub fn new() -> Self {
+        Self { multi: MultiProgress::new() }
+    }
+
+    pub fn spinner(&self, name: &str) -> ProgressBar {
+        let bar = self.multi.add(ProgressBar::new_spinner());
+        bar.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner} {prefix:.bold} {msg}")
+                .expect("valid progress style")
+                .tick_chars("⠁⠂⠄⡀⢀⠠⠐⠈ "),
+        );
+        bar.set_prefix(name.to_string());
+        bar.enable_steady_tick(std::time::Duration::from_millis(100));
+        bar.set_message(Phase::Resolve.label());
+        bar
+    }
+}
+
+pub fn advance(bar: &ProgressBar, phase: Phase) {
+    bar.set_message(phase.label());
+}
@@ -0,0 +1,97 @@
+//! Variable substitution for `forge init --template`.
+//!
+//! Community templates ship placeholders like `{{ name }}` or `{{ author }}` instead of requiring
+//! a manual find-and-replace after cloning. [`Context::expand_tree`] walks a freshly cloned
+//! template and fills them in; [`Context::render`] does the same for a single scaffolded file.
+
+use regex::Regex;
+use std::{collections::HashMap, path::Path, process::Command};
+
+/// The values a template's placeholders are substituted with.
+#[derive(Debug, Clone)]
+pub struct Context {
+    values: HashMap<String, String>,
+}
+
+impl Context {
+    /// Builds the substitution context for `root`, preferring explicit CLI overrides and falling
+    /// back to `git config` / the root directory's name.
+    pub fn new(root: &Path, name: Option<String>, author: Option<String>, license: Option<String>) -> Self {
+        let mut values = HashMap::new();
+        values.insert(
+            "name".to_string(),
+            name.unwrap_or_else(|| {
+                root.file_name().and_then(|name| name.to_str()).unwrap_or("project").to_string()
+            }),
+        );
+        values.insert(
+            "author".to_string(),
+            author
+                .or_else(|| git_config("user.name"))
+                .or_else(|| git_config("user.email"))
+                .unwrap_or_default(),
+        );
+        values.insert("license".to_string(), license.unwrap_or_else(|| "UNLICENSED".to_string()));
+        values.insert("solc_version".to_string(), "^0.8.10".to_string());
+        Self { values }
+    }
+
+    /// Replaces every `{{ key }}` placeholder in `input` with its value, leaving unknown keys
+    /// untouched.
+    pub fn render(&self, input: &str) -> String {
+        let placeholder = Regex::new(r"\{\{\s*([a-zA-Z0-9_]+)\s*\}\}").unwrap();
+        placeholder
+            .replace_all(input, |captures: &regex::Captures| {
+                let key = &captures[1];
+                self.values.get(key).cloned().unwrap_or_else(|| captures[0].to_string())
+            })
+            .into_owned()
+    }
+
+    /// Walks every text file under `root` (skipping `.git`) and expands placeholders in place.
+    pub fn expand_tree(&self, root: &Path) -> eyre::Result<()> {
+        for entry in walk(root)? {
+            let contents = match std::fs::read_to_string(&entry) {
+                Ok(contents) => contents,
+                // skip files that aren't valid utf8 (binaries, images, ...)
+                Err(_) => continue,
+            };
+            let rendered = self.render(&contents);
+            if rendered != contents {
+                std::fs::write(&entry, rendered)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Recursively lists every file under `root`, skipping `.git` directories.
+fn walk(root: &Path) -> eyre::Result<Vec<std::path::PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(root)? {
+        let entry = entry?;
+        let path = entry.path();
+        if path.is_dir() {
+            if path.file_name().and_then(|name| name.to_str()) == Some(".git") {
+                continue
+            }
+            files.extend(walk(&path)?);
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(files)
+}
+
+fn git_config(key: &str) -> Option<String> {
+    let output = Command::new("git").args(&["config", "--get", key]).output().ok()?;
+    if !output.status.success() {
+        return None
+    }
+    let value = String::from_utf8(output.stdout).ok()?.trim().to_string();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
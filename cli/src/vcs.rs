@@ -0,0 +1,195 @@
+//! Pluggable version control backends for `forge install`/`update`/`init`.
+//!
+//! All three commands used to spawn `git` directly. Routing them through [`VcsBackend`] instead
+//! means a dependency hosted on a Mercurial forge works the same way, and a third-party backend
+//! can be added without touching the CLI plumbing. Staging and committing in the outer
+//! repository (`git init`, `git add`, `git commit`) still spawn `git` directly.
+
+use crate::forge_opts::VcsKind;
+use std::path::Path;
+use std::process::Command;
+
+/// A source control backend capable of vendoring a dependency into `lib/`.
+pub trait VcsBackend {
+    /// Clones `source` into `dest`, optionally recursing into nested submodules/subrepos.
+    fn clone(&self, source: &str, dest: &Path, recursive: bool) -> eyre::Result<()>;
+
+    /// Registers an already-cloned `dest`, originally cloned from `source`, as a dependency of
+    /// the current repository (e.g. as a git submodule pointing at `source`, not at `dest`).
+    fn add_dependency(&self, source: &str, dest: &Path) -> eyre::Result<()>;
+
+    /// Updates `path`, or every dependency if `path` is `None`.
+    fn update(&self, path: Option<&Path>) -> eyre::Result<()>;
+
+    /// Checks out `tag` (a tag, branch, or revision) inside `path`.
+    fn checkout(&self, path: &Path, tag: &str) -> eyre::Result<()>;
+
+    /// Returns the name of the branch currently checked out in `path`.
+    fn current_branch(&self, path: &Path) -> eyre::Result<String>;
+
+    /// Resolves `spec` (a tag, branch, `HEAD`, or a `rev=<sha>` spec) against `source` to a
+    /// concrete, immutable revision, without requiring a local clone. Used to pin a
+    /// [`crate::lockfile::LockedDependency`] before it is ever fetched.
+    fn resolve_rev(&self, source: &str, spec: &str) -> eyre::Result<String>;
+}
+
+/// Runs `cmd`, bailing with its stderr if it did not exit successfully.
+fn run(mut cmd: Command) -> eyre::Result<()> {
+    let output = cmd.output()?;
+    if !output.status.success() {
+        eyre::bail!(
+            "`{:?}` failed with {}: {}",
+            cmd,
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    Ok(())
+}
+
+/// Backend wrapping the `git` CLI.
+pub struct Git;
+
+impl VcsBackend for Git {
+    fn clone(&self, source: &str, dest: &Path, recursive: bool) -> eyre::Result<()> {
+        let mut cmd = Command::new("git");
+        cmd.arg("clone");
+        if recursive {
+            cmd.arg("--recursive");
+        }
+        cmd.args(&[source, &dest.display().to_string()]);
+        run(cmd)
+    }
+
+    fn add_dependency(&self, source: &str, dest: &Path) -> eyre::Result<()> {
+        // `dest` already holds a full clone (see `clone` above), so this just records the
+        // submodule's upstream `source` and gitlink in `.gitmodules`/the index without
+        // re-fetching anything. Pointing `.gitmodules` at the local clone path instead of
+        // `source` would break `git submodule update --init` for anyone else who clones the
+        // superproject.
+        let mut cmd = Command::new("git");
+        cmd.args(&["submodule", "add", source, &dest.display().to_string()]);
+        run(cmd)
+    }
+
+    fn update(&self, path: Option<&Path>) -> eyre::Result<()> {
+        let mut cmd = Command::new("git");
+        cmd.args(&["submodule", "update", "--init", "--recursive"]);
+        if let Some(path) = path {
+            cmd.arg(&path.display().to_string());
+        }
+        run(cmd)
+    }
+
+    fn checkout(&self, path: &Path, tag: &str) -> eyre::Result<()> {
+        let mut cmd = Command::new("git");
+        cmd.args(&["checkout", "--recurse-submodules", tag]).current_dir(path);
+        run(cmd)
+    }
+
+    fn current_branch(&self, path: &Path) -> eyre::Result<String> {
+        let output = Command::new("git")
+            .args(&["rev-parse", "--abbrev-ref", "HEAD"])
+            .current_dir(path)
+            .output()?;
+        if !output.status.success() {
+            eyre::bail!(
+                "failed to determine the current branch of {}: {}",
+                path.display(),
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(String::from_utf8(output.stdout)?.trim().to_string())
+    }
+
+    fn resolve_rev(&self, source: &str, spec: &str) -> eyre::Result<String> {
+        if let Some(rev) = spec.strip_prefix("rev=") {
+            return Ok(rev.to_string())
+        }
+        let output = Command::new("git").args(&["ls-remote", source, spec]).output()?;
+        if !output.status.success() {
+            eyre::bail!(
+                "failed to resolve `{}` for {}: {}",
+                spec,
+                source,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        String::from_utf8(output.stdout)?
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().next())
+            .map(str::to_string)
+            .ok_or_else(|| eyre::eyre!("could not resolve `{}` for {}: no matching ref", spec, source))
+    }
+}
+
+/// Backend wrapping the `hg` (Mercurial) CLI.
+pub struct Mercurial;
+
+impl VcsBackend for Mercurial {
+    fn clone(&self, source: &str, dest: &Path, _recursive: bool) -> eyre::Result<()> {
+        let mut cmd = Command::new("hg");
+        cmd.args(&["clone", source, &dest.display().to_string()]);
+        run(cmd)
+    }
+
+    fn add_dependency(&self, _source: &str, _dest: &Path) -> eyre::Result<()> {
+        // Mercurial has no submodule-style registration step; the clone is simply nested on
+        // disk. Nothing records it in an `.hgsub` file.
+        Ok(())
+    }
+
+    fn update(&self, path: Option<&Path>) -> eyre::Result<()> {
+        let mut cmd = Command::new("hg");
+        cmd.arg("update");
+        if let Some(path) = path {
+            cmd.current_dir(path);
+        }
+        run(cmd)
+    }
+
+    fn checkout(&self, path: &Path, tag: &str) -> eyre::Result<()> {
+        let mut cmd = Command::new("hg");
+        cmd.args(&["update", tag]).current_dir(path);
+        run(cmd)
+    }
+
+    fn current_branch(&self, path: &Path) -> eyre::Result<String> {
+        let output = Command::new("hg").arg("branch").current_dir(path).output()?;
+        if !output.status.success() {
+            eyre::bail!(
+                "failed to determine the current branch of {}: {}",
+                path.display(),
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(String::from_utf8(output.stdout)?.trim().to_string())
+    }
+
+    fn resolve_rev(&self, source: &str, spec: &str) -> eyre::Result<String> {
+        if let Some(rev) = spec.strip_prefix("rev=") {
+            return Ok(rev.to_string())
+        }
+        let spec = if spec == "HEAD" { "tip" } else { spec };
+        let output = Command::new("hg").args(&["identify", "--id", "--rev", spec, source]).output()?;
+        if !output.status.success() {
+            eyre::bail!(
+                "failed to resolve `{}` for {}: {}",
+                spec,
+                source,
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(String::from_utf8(output.stdout)?.trim().to_string())
+    }
+}
+
+/// Picks the backend for a dependency: an explicit per-dependency [`VcsKind`] wins, followed by
+/// the `--vcs` flag passed to the command, defaulting to [`Git`].
+pub fn backend_for(dependency: Option<VcsKind>, fallback: Option<VcsKind>) -> Box<dyn VcsBackend> {
+    match dependency.or(fallback) {
+        Some(VcsKind::Mercurial) => Box::new(Mercurial),
+        Some(VcsKind::Git) | None => Box::new(Git),
+    }
+}